@@ -1,7 +1,11 @@
 //! Semaphore.
 
-use std::sync::{Condvar, Mutex};
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 
 /// Semaphore.
 pub struct Semaphore {
@@ -35,19 +39,21 @@ impl Semaphore {
     /// Tries to acquire the 'Semaphore' for the duration specified (blocking operation)
     /// and returns true on success and false on failure.
     pub fn acquire_timeout(&self, dur: Duration) -> bool {
+        let deadline = Instant::now() + dur;
         let mut count = self.lock.lock().unwrap();
-        match self.cvar.wait_timeout(count, dur) {
-            Ok((new_count, _)) => {
-                count = new_count;
-                if *count > 0 {
-                    *count -= 1;
-                    true
-                } else {
-                    false
-                }
+        while *count == 0 {
+            let remaining = match deadline.checked_duration_since(Instant::now()) {
+                Some(remaining) => remaining,
+                None => return false,
+            };
+            let (new_count, timeout_result) = self.cvar.wait_timeout(count, remaining).unwrap();
+            count = new_count;
+            if timeout_result.timed_out() && *count == 0 {
+                return false;
             }
-            _ => false,
         }
+        *count -= 1;
+        true
     }
 
     /// Tries to acquire the 'Semaphore' immediately (non-blocking operation)
@@ -63,15 +69,47 @@ impl Semaphore {
     }
 
     /// Releases the 'Semaphore'.
+    ///
+    /// Uses `notify_all` rather than `notify_one` because `acquire_many` waiters can
+    /// be parked on the same 'Condvar' with a threshold greater than one permit; a
+    /// single-wakeup release could wake an unsatisfiable `acquire_many` waiter while
+    /// starving a satisfiable `acquire` waiter.
     pub fn release(&self) {
         *self.lock.lock().unwrap() += 1;
-        self.cvar.notify_one();
+        self.cvar.notify_all();
     }
 
     /// Returns current value of the 'Semaphore''s counter.
     pub fn get_value(&self) -> usize {
         *self.lock.lock().unwrap()
     }
+
+    /// Acquires `n` permits of the 'Semaphore' atomically (blocking operation).
+    pub fn acquire_many(&self, n: usize) {
+        let mut count = self.lock.lock().unwrap();
+        while *count < n {
+            count = self.cvar.wait(count).unwrap();
+        }
+        *count -= n;
+    }
+
+    /// Tries to acquire `n` permits of the 'Semaphore' immediately (non-blocking operation)
+    /// and returns true on success and false on failure.
+    pub fn try_acquire_many(&self, n: usize) -> bool {
+        let mut count = self.lock.lock().unwrap();
+        if *count >= n {
+            *count -= n;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Releases `n` permits of the 'Semaphore' atomically.
+    pub fn release_many(&self, n: usize) {
+        *self.lock.lock().unwrap() += n;
+        self.cvar.notify_all();
+    }
 }
 
 impl<'a> SemaphoreGuard<'a> {
@@ -88,3 +126,339 @@ impl<'a> Drop for SemaphoreGuard<'a> {
         self.semaphore.release();
     }
 }
+
+/// Owned 'Semaphore' guard backed by an 'Arc<Semaphore>', with no lifetime parameter
+/// so it can be moved into a spawned thread or stored in a struct.
+pub struct SemaphoreGuardArc {
+    semaphore: Arc<Semaphore>,
+}
+
+impl SemaphoreGuardArc {
+    /// Acquires the 'Semaphore' and returns an owned 'SemaphoreGuardArc'.
+    pub fn acquire_arc(semaphore: Arc<Semaphore>) -> Self {
+        semaphore.acquire();
+        SemaphoreGuardArc { semaphore }
+    }
+}
+
+impl Drop for SemaphoreGuardArc {
+    /// Releases the acquired 'Semaphore'.
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+/// Async semaphore.
+pub struct AsyncSemaphore {
+    state: Mutex<AsyncSemaphoreState>,
+}
+
+struct AsyncSemaphoreState {
+    count: usize,
+    next_waiter_id: u64,
+    wakers: VecDeque<(u64, Waker)>,
+}
+
+/// Async semaphore guard.
+pub struct AsyncSemaphoreGuard<'a> {
+    semaphore: &'a AsyncSemaphore,
+}
+
+/// Future returned by 'AsyncSemaphore::acquire_async'.
+pub struct AcquireFuture<'a> {
+    semaphore: &'a AsyncSemaphore,
+    waiter_id: Option<u64>,
+}
+
+impl AsyncSemaphore {
+    /// Creates a new 'AsyncSemaphore' with a specific counter value.
+    pub fn new(count: usize) -> AsyncSemaphore {
+        AsyncSemaphore {
+            state: Mutex::new(AsyncSemaphoreState {
+                count,
+                next_waiter_id: 0,
+                wakers: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Acquires the 'AsyncSemaphore' (async operation) and returns a future resolving
+    /// to an 'AsyncSemaphoreGuard' once a permit is available.
+    pub fn acquire_async(&self) -> AcquireFuture<'_> {
+        AcquireFuture {
+            semaphore: self,
+            waiter_id: None,
+        }
+    }
+
+    /// Releases the 'AsyncSemaphore', granting the permit and waking one queued
+    /// waiter (if any) so its next poll observes the incremented count.
+    pub fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.count += 1;
+        if let Some((_, waker)) = state.wakers.pop_front() {
+            waker.wake();
+        }
+    }
+
+    /// Returns current value of the 'AsyncSemaphore''s counter.
+    pub fn get_value(&self) -> usize {
+        self.state.lock().unwrap().count
+    }
+}
+
+impl<'a> Future for AcquireFuture<'a> {
+    type Output = AsyncSemaphoreGuard<'a>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = this.semaphore.state.lock().unwrap();
+        if state.count > 0 {
+            state.count -= 1;
+            if let Some(waiter_id) = this.waiter_id.take() {
+                state.wakers.retain(|(id, _)| *id != waiter_id);
+            }
+            return Poll::Ready(AsyncSemaphoreGuard {
+                semaphore: this.semaphore,
+            });
+        }
+        match this.waiter_id {
+            Some(waiter_id) => {
+                if let Some((_, waker)) = state.wakers.iter_mut().find(|(id, _)| *id == waiter_id)
+                {
+                    *waker = cx.waker().clone();
+                } else {
+                    state.wakers.push_back((waiter_id, cx.waker().clone()));
+                }
+            }
+            None => {
+                let waiter_id = state.next_waiter_id;
+                state.next_waiter_id += 1;
+                state.wakers.push_back((waiter_id, cx.waker().clone()));
+                this.waiter_id = Some(waiter_id);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<'a> Drop for AcquireFuture<'a> {
+    /// Deregisters the queued waker, if any, so a cancelled acquire does not
+    /// silently eat a future release.
+    fn drop(&mut self) {
+        if let Some(waiter_id) = self.waiter_id {
+            let mut state = self.semaphore.state.lock().unwrap();
+            state.wakers.retain(|(id, _)| *id != waiter_id);
+        }
+    }
+}
+
+impl<'a> Drop for AsyncSemaphoreGuard<'a> {
+    /// Releases the acquired 'AsyncSemaphore'.
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+/// Binary semaphore, capped at a single permit, usable as a mutex replacement.
+/// Unlike 'Semaphore', 'release' saturates at 1 instead of incrementing without bound,
+/// so a double release cannot turn it into a multi-permit semaphore.
+pub struct BinarySemaphore {
+    lock: Mutex<bool>,
+    cvar: Condvar,
+}
+
+/// Binary semaphore guard.
+pub struct BinarySemaphoreGuard<'a> {
+    semaphore: &'a BinarySemaphore,
+}
+
+impl BinarySemaphore {
+    /// Creates a new 'BinarySemaphore', available if `locked` is false.
+    pub fn new(locked: bool) -> BinarySemaphore {
+        BinarySemaphore {
+            lock: Mutex::new(!locked),
+            cvar: Condvar::new(),
+        }
+    }
+
+    /// Acquires the 'BinarySemaphore' (blocking operation).
+    pub fn acquire(&self) {
+        let mut available = self.lock.lock().unwrap();
+        while !*available {
+            available = self.cvar.wait(available).unwrap();
+        }
+        *available = false;
+    }
+
+    /// Tries to acquire the 'BinarySemaphore' immediately (non-blocking operation)
+    /// and returns true on success and false on failure.
+    pub fn try_acquire(&self) -> bool {
+        let mut available = self.lock.lock().unwrap();
+        if *available {
+            *available = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Releases the 'BinarySemaphore', saturating at a single permit.
+    pub fn release(&self) {
+        let mut available = self.lock.lock().unwrap();
+        if !*available {
+            *available = true;
+            self.cvar.notify_one();
+        }
+    }
+
+    /// Acquires the 'BinarySemaphore' and returns a 'BinarySemaphoreGuard',
+    /// letting it be used as a mutex replacement. 'Condvar' makes no FIFO
+    /// guarantee, so this does not provide fair/starvation-free wakeup order.
+    pub fn lock(&self) -> BinarySemaphoreGuard<'_> {
+        BinarySemaphoreGuard::acquire(self)
+    }
+
+    /// Returns the number of free permits of the 'BinarySemaphore' (1 or 0).
+    pub fn get_value(&self) -> usize {
+        if *self.lock.lock().unwrap() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl<'a> BinarySemaphoreGuard<'a> {
+    /// Acquires the 'BinarySemaphore' and returns a 'BinarySemaphoreGuard'.
+    pub fn acquire(semaphore: &'a BinarySemaphore) -> Self {
+        semaphore.acquire();
+        BinarySemaphoreGuard { semaphore }
+    }
+}
+
+impl<'a> Drop for BinarySemaphoreGuard<'a> {
+    /// Releases the acquired 'BinarySemaphore'.
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn release_wakes_a_smaller_waiting_acquire_not_just_acquire_many() {
+        let semaphore = Arc::new(Semaphore::new(0));
+
+        // An acquire_many(2) waiter whose threshold a single release can't satisfy...
+        let many_waiter = Arc::clone(&semaphore);
+        let many_handle = thread::spawn(move || {
+            many_waiter.acquire_many(2);
+        });
+
+        // ...parked on the same Condvar as a plain acquire(1) waiter that can be
+        // satisfied by one released permit.
+        let single_waiter = Arc::clone(&semaphore);
+        let single_handle = thread::spawn(move || {
+            single_waiter.acquire();
+        });
+
+        // Give both threads time to block before releasing a single permit.
+        thread::sleep(Duration::from_millis(50));
+        semaphore.release();
+
+        // Under a single-wakeup notify_one this would starve: notify_one could wake
+        // the unsatisfiable acquire_many(2) waiter instead and this join would hang.
+        single_handle.join().unwrap();
+        assert_eq!(semaphore.get_value(), 0);
+
+        // Release the two permits the acquire_many(2) waiter still needs so it can
+        // also finish and the test doesn't leak a blocked thread.
+        semaphore.release_many(2);
+        many_handle.join().unwrap();
+    }
+
+    #[test]
+    fn acquire_arc_releases_on_drop_across_threads() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let guard = SemaphoreGuardArc::acquire_arc(Arc::clone(&semaphore));
+        assert_eq!(semaphore.get_value(), 0);
+
+        // The whole point of acquire_arc is that the guard has no lifetime tied to
+        // a stack-local Semaphore, so it can be moved into a spawned thread.
+        let handle = thread::spawn(move || drop(guard));
+        handle.join().unwrap();
+
+        assert_eq!(semaphore.get_value(), 1);
+    }
+
+    #[test]
+    fn acquire_timeout_succeeds_immediately_when_permit_available() {
+        let semaphore = Semaphore::new(1);
+        assert!(semaphore.acquire_timeout(Duration::from_millis(50)));
+        assert_eq!(semaphore.get_value(), 0);
+    }
+
+    #[test]
+    fn acquire_timeout_succeeds_when_released_before_deadline() {
+        let semaphore = Arc::new(Semaphore::new(0));
+        let releaser = Arc::clone(&semaphore);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            releaser.release();
+        });
+
+        // The deadline loop must re-check `*count` on each wakeup instead of
+        // trusting a stale `timed_out()` from an earlier spurious wakeup.
+        assert!(semaphore.acquire_timeout(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn acquire_timeout_fails_after_deadline_elapses_with_no_permit() {
+        let semaphore = Semaphore::new(0);
+        assert!(!semaphore.acquire_timeout(Duration::from_millis(50)));
+        assert_eq!(semaphore.get_value(), 0);
+    }
+
+    #[test]
+    fn binary_semaphore_release_saturates_at_one_permit() {
+        let semaphore = BinarySemaphore::new(false);
+        semaphore.acquire();
+
+        // A double release must not push the permit count above one.
+        semaphore.release();
+        semaphore.release();
+        assert_eq!(semaphore.get_value(), 1);
+
+        // The single saturated permit can be acquired once...
+        assert!(semaphore.try_acquire());
+        // ...and a second acquire still finds none available.
+        assert!(!semaphore.try_acquire());
+    }
+
+    #[test]
+    fn async_semaphore_release_grants_permit_to_pending_waiter() {
+        let semaphore = AsyncSemaphore::new(0);
+        let mut future = pin!(semaphore.acquire_async());
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // No permits yet: the future must stay pending and register a waker.
+        assert!(matches!(future.as_mut().poll(&mut cx), Poll::Pending));
+        assert_eq!(semaphore.get_value(), 0);
+
+        // Releasing must grant the permit, not just relay a wakeup.
+        semaphore.release();
+        assert_eq!(semaphore.get_value(), 1);
+
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(guard) => drop(guard),
+            Poll::Pending => panic!("acquire_async did not observe the released permit"),
+        };
+    }
+}